@@ -1,53 +1,151 @@
 use std::fs;
 use std::io::{self, Read, Write};
-use std::net::{TcpListener, TcpStream};
+use std::net::TcpListener;
 use std::sync::{Arc, Mutex};
-use std::thread;
-use std::time::{Duration, SystemTime, UNIX_EPOCH};
-use std::collections::HashMap;
+use std::time::Duration;
 use std::path::Path;
 
+mod middleware;
+mod pool;
+mod request;
+mod response;
+mod tls;
+mod websocket;
+
+use middleware::cors::Cors;
+use middleware::csrf::CsrfOriginCheck;
+use middleware::logging::Logging;
+use middleware::rate_limit::RateLimiter;
+use middleware::Chain;
+use pool::ThreadPool;
+use request::Request;
+use response::Response;
+
 const PORT: u16 = 8000;
 const MAX_CONCURRENT_CONNECTIONS: usize = 4;
 const RATE_LIMIT_WINDOW: Duration = Duration::from_secs(60);
 const MAX_REQUESTS_PER_WINDOW: usize = 100;
 
+/// How long a keep-alive connection may sit idle between requests
+/// before the worker gives up and closes it.
+const KEEP_ALIVE_TIMEOUT: Duration = Duration::from_secs(15);
+
+/// Files larger than this are streamed with `Transfer-Encoding: chunked`
+/// instead of being buffered fully in memory.
+const STREAMING_THRESHOLD: u64 = 1024 * 1024;
+
+/// Index filenames tried in order when a request maps to a directory,
+/// before falling back to a generated directory listing.
+const INDEX_FILENAMES: [&str; 3] = ["index.html", "index.htm", "index.txt"];
+
+/// Listening port and, if present, the TLS settings to terminate
+/// connections with. Read from the environment rather than hard-coded
+/// so the same binary can serve plaintext or HTTPS.
+struct ServerConfig {
+    port: u16,
+    tls: Option<tls::TlsConfig>,
+    /// Origins allowed to make state-changing requests, read from
+    /// `CSRF_TRUSTED_ORIGINS`. Empty by default, which means the CSRF
+    /// check rejects every non-GET/HEAD/OPTIONS request until a
+    /// deployment opts in.
+    csrf_trusted_origins: Vec<String>,
+}
+
+impl ServerConfig {
+    /// Reads `PORT` (default 8000), enables TLS only when both
+    /// `TLS_CERT_PATH` and `TLS_KEY_PATH` are set, and reads
+    /// `CSRF_TRUSTED_ORIGINS` as a comma-separated list of origins
+    /// trusted for state-changing requests.
+    fn from_env() -> Self {
+        let port = std::env::var("PORT")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(PORT);
+
+        let tls = match (std::env::var("TLS_CERT_PATH"), std::env::var("TLS_KEY_PATH")) {
+            (Ok(cert_path), Ok(key_path)) => Some(tls::TlsConfig { cert_path, key_path }),
+            _ => None,
+        };
+
+        let csrf_trusted_origins = std::env::var("CSRF_TRUSTED_ORIGINS")
+            .ok()
+            .map(|origins| parse_trusted_origins(&origins))
+            .unwrap_or_default();
+
+        ServerConfig { port, tls, csrf_trusted_origins }
+    }
+}
+
+/// Parses `CSRF_TRUSTED_ORIGINS` as a comma-separated list of origins,
+/// trimming whitespace and dropping empty entries.
+fn parse_trusted_origins(value: &str) -> Vec<String> {
+    value.split(',').map(str::trim).filter(|origin| !origin.is_empty()).map(str::to_string).collect()
+}
+
+/// Builds the middleware chain that wraps every request: logging runs
+/// outermost so it sees the final response, then CORS and the CSRF
+/// origin check, then rate limiting innermost, right before the
+/// terminal file handler.
+fn build_middleware(csrf_trusted_origins: Vec<String>) -> Chain {
+    Chain::new()
+        .with(Logging)
+        .with(Cors::permissive())
+        .with(CsrfOriginCheck { trusted_origins: csrf_trusted_origins })
+        .with(RateLimiter::new(RATE_LIMIT_WINDOW, MAX_REQUESTS_PER_WINDOW))
+}
+
 fn main() -> io::Result<()> {
-    let listener = TcpListener::bind(format!("0.0.0.0:{}", PORT))?;
+    let config = ServerConfig::from_env();
+    let listener = TcpListener::bind(format!("0.0.0.0:{}", config.port))?;
     let visitor_count = Arc::new(Mutex::new(0));
-    let ip_requests = Arc::new(Mutex::new(HashMap::new()));
-    
-    println!("Server running on port {}", PORT);
-    
-    // Create a thread pool with a fixed size
-    let mut thread_pool = Vec::with_capacity(MAX_CONCURRENT_CONNECTIONS);
-    
+    let middleware = Arc::new(build_middleware(config.csrf_trusted_origins.clone()));
+
+    let tls_config = match &config.tls {
+        Some(cfg) => Some(tls::load_server_config(cfg)?),
+        None => None,
+    };
+
+    println!(
+        "Server running on port {}{}",
+        config.port,
+        if tls_config.is_some() { " (TLS)" } else { "" }
+    );
+
+    // A fixed-size pool of long-lived workers, fed by a channel of
+    // accepted sockets, so one slow keep-alive client can't serialize
+    // the rest behind it the way popping a random thread handle did.
+    let pool = ThreadPool::new(MAX_CONCURRENT_CONNECTIONS);
+
     // Accept connections indefinitely
     for stream in listener.incoming() {
         match stream {
             Ok(stream) => {
                 let visitor_count = Arc::clone(&visitor_count);
-                let ip_requests = Arc::clone(&ip_requests);
-                
-                // Clean up finished threads - FIX APPLIED HERE
-                thread_pool.retain(|handle: &thread::JoinHandle<_>| !handle.is_finished());
-                
-                // If we've reached max concurrent connections, wait for one to finish
-                if thread_pool.len() >= MAX_CONCURRENT_CONNECTIONS {
-                    if let Some(handle) = thread_pool.pop() {
-                        if let Err(e) = handle.join() {
-                            eprintln!("Error joining thread: {:?}", e);
-                        }
-                    }
+                let middleware = Arc::clone(&middleware);
+                let tls_config = tls_config.clone();
+
+                if let Err(e) = stream.set_read_timeout(Some(KEEP_ALIVE_TIMEOUT)) {
+                    eprintln!("Failed to set read timeout: {}", e);
                 }
-                
-                // Handle the new connection in a thread
-                let handle = thread::spawn(move || {
-                    if let Err(e) = handle_client(stream, visitor_count, ip_requests) {
+
+                pool.execute(move || {
+                    let client_ip = stream
+                        .peer_addr()
+                        .map(|addr| addr.ip().to_string())
+                        .unwrap_or_else(|_| "unknown".to_string());
+
+                    let result = match tls_config {
+                        Some(cfg) => match tls::accept(&cfg, stream) {
+                            Ok(tls_stream) => handle_client(tls_stream, client_ip, visitor_count, middleware),
+                            Err(e) => Err(e),
+                        },
+                        None => handle_client(stream, client_ip, visitor_count, middleware),
+                    };
+
+                    if let Err(e) = result {
                         eprintln!("Error handling client: {}", e);
                     }
                 });
-                thread_pool.push(handle);
             },
             Err(e) => {
                 eprintln!("Error accepting connection: {}", e);
@@ -58,222 +156,275 @@ fn main() -> io::Result<()> {
     Ok(())
 }
 
-fn handle_client(
-    mut stream: TcpStream, 
-    visitor_count: Arc<Mutex<u32>>, 
-    ip_requests: Arc<Mutex<HashMap<String, Vec<SystemTime>>>>
+/// Serves one connection end to end. Generic over `Read + Write` so the
+/// same request-handling path serves both plaintext `TcpStream`s and
+/// TLS-wrapped streams.
+///
+/// Loops reading further requests off the same socket as long as the
+/// client asks for keep-alive, stopping on `Connection: close`, a read
+/// timeout, or the client closing the connection.
+fn handle_client<S: Read + Write>(
+    mut stream: S,
+    client_ip: String,
+    visitor_count: Arc<Mutex<u32>>,
+    middleware: Arc<Chain>,
 ) -> io::Result<()> {
-    // Read the HTTP request
-    let mut buffer = [0; 4096]; // Larger buffer for bigger requests
-    let bytes_read = match stream.read(&mut buffer) {
-        Ok(n) => n,
-        Err(e) => {
-            eprintln!("Failed to read from stream: {}", e);
-            return Err(e);
-        }
-    };
-    
-    if bytes_read == 0 {
-        return Ok(()); // Empty request, client disconnected
-    }
-    
-    let request_str = String::from_utf8_lossy(&buffer[..bytes_read]);
-    
-    // Get client IP for rate limiting and logging
-    let client_ip = match stream.peer_addr() {
-        Ok(addr) => addr.ip().to_string(),
-        Err(_) => "unknown".to_string(),
-    };
-    
-    // Parse the request to get the path
-    let request_lines: Vec<&str> = request_str.lines().collect();
-    if request_lines.is_empty() {
-        return Ok(());
-    }
-    
-    let first_line = request_lines[0];
-    let parts: Vec<&str> = first_line.split_whitespace().collect();
-    
-    if parts.len() < 2 {
-        return send_error(&mut stream, 400, "Bad Request");
-    }
-    
-    let method = parts[0];
-    let path = parts[1];
-    
-    // Print request to console
-    println!("[{}] {} {} from {}", 
-        format_timestamp(SystemTime::now()),
-        method,
-        path,
-        client_ip
-    );
-    
-    // Rate limit check
-    if !rate_limit(&client_ip, &ip_requests) {
-        println!("Rate limit exceeded for {}", client_ip);
-        let response = "HTTP/1.1 429 Too Many Requests\r\n\
-                       Content-Length: 19\r\n\
-                       Content-Type: text/plain\r\n\
-                       Access-Control-Allow-Origin: *\r\n\
-                       \r\n\
-                       Rate limit exceeded";
-        stream.write_all(response.as_bytes())?;
-        return Ok(());
-    }
-    
-    // Special endpoint for visitor count
-    if path == "/visitor-count" {
-        // Always increment the counter for now (for testing)
-        let count = match visitor_count.lock() {
-            Ok(mut guard) => {
-                // Always increment for now
-                *guard += 1;
-                println!("Incrementing visitor count to: {}", *guard);
-                *guard // Return the current value
-            },
-            Err(_) => {
-                eprintln!("Visitor count mutex was poisoned");
-                return send_error(&mut stream, 500, "Internal Server Error");
+    // Bytes read past one request's boundary (a pipelined next request
+    // arriving in the same read) carry over to the next iteration
+    // instead of being dropped.
+    let mut leftover = Vec::new();
+
+    loop {
+        let mut request = match Request::from_stream(&mut stream, &mut leftover) {
+            Ok(Some(request)) => request,
+            Ok(None) => return Ok(()), // Client closed the connection
+            Err(e) if e.kind() == io::ErrorKind::WouldBlock || e.kind() == io::ErrorKind::TimedOut => {
+                return Ok(()); // Idle keep-alive connection timed out
+            }
+            Err(e) => {
+                eprintln!("Failed to read from stream: {}", e);
+                return Err(e);
             }
         };
-        
-        let body = format!("{}", count);
-        
-        let header = format!(
-            "HTTP/1.1 200 OK\r\n\
-            Content-Length: {}\r\n\
-            Content-Type: text/plain\r\n\
-            Access-Control-Allow-Origin: *\r\n\
-            Access-Control-Allow-Methods: GET, POST, OPTIONS\r\n\
-            Access-Control-Allow-Headers: Content-Type\r\n\
-            \r\n",
-            body.len()
-        );
-        
-        stream.write_all(header.as_bytes())?;
-        stream.write_all(body.as_bytes())?;
-        return Ok(());
+        request.client_ip = client_ip.clone();
+
+        let keep_alive = request.wants_keep_alive();
+        let is_upgrade_request = websocket::is_upgrade_request(&request);
+
+        // The upgrade handshake is produced by the same terminal closure
+        // as every other response, so it still passes through the full
+        // middleware chain (logging, rate limiting) instead of
+        // special-casing the upgrade ahead of it.
+        let response = middleware.run(&request, |request| {
+            if websocket::is_upgrade_request(request) {
+                websocket::handshake_response(request.header("sec-websocket-key").unwrap_or_default())
+            } else {
+                terminal_handler(request, &visitor_count)
+            }
+        });
+
+        let upgraded = is_upgrade_request && response.status == 101;
+        response.write_to(&mut stream)?;
+
+        // A successful WebSocket handshake takes over the connection
+        // entirely: once we switch protocols there are no more HTTP
+        // requests to read. An upgrade attempt that a middleware
+        // short-circuited (e.g. rate limited) stays plain HTTP.
+        if upgraded {
+            return websocket::echo_loop(&mut stream);
+        }
+
+        if !keep_alive {
+            return Ok(());
+        }
     }
-    
-    // Handle OPTIONS request for CORS preflight
-    if method == "OPTIONS" {
-        return send_cors_preflight(&mut stream);
+}
+
+/// The handler at the center of the middleware chain: serves the
+/// visitor-count endpoint and static files from `public_html`. Cross-
+/// cutting concerns (CORS, CSRF, rate limiting, logging) are applied
+/// by the middleware around this, so this only deals with request
+/// routing and file I/O.
+fn terminal_handler(request: &Request, visitor_count: &Arc<Mutex<u32>>) -> Response {
+    let method = request.method.as_str();
+    let path = request.path.as_str();
+
+    // Special endpoint for visitor count
+    if path == "/visitor-count" {
+        return handle_visitor_count(visitor_count);
     }
-    
+
     // Only handle GET requests for simplicity
     if method != "GET" {
-        return send_error(&mut stream, 405, "Method Not Allowed");
+        return error_response(405, "Method Not Allowed");
     }
-    
+
     // Determine the requested file path
     let requested_path = if path == "/" {
-        "public_html/index.html".to_string()
+        "public_html".to_string()
     } else {
         format!("public_html{}", path)
     };
-    
+
     // Security check to prevent directory traversal
     let path_obj = Path::new(&requested_path);
     if path_obj.components().any(|c| c.as_os_str() == "..") {
         println!("Security: Blocked path with .. component: {}", requested_path);
-        return send_not_found(&mut stream);
+        return not_found_response();
     }
-    
-    // Try to read the requested file as binary data
-    match fs::read(&requested_path) {
-        Ok(contents) => {
-            let content_type = get_content_type(&requested_path);
-            
-            let header = format!(
-                "HTTP/1.1 200 OK\r\n\
-                Content-Length: {}\r\n\
-                Content-Type: {}\r\n\
-                Access-Control-Allow-Origin: *\r\n\
-                Access-Control-Allow-Methods: GET, POST, OPTIONS\r\n\
-                Access-Control-Allow-Headers: Content-Type\r\n\
-                \r\n",
-                contents.len(),
-                content_type
-            );
-            
-            // Log successful request to file and print status to console
-            println!("[{}] 200 OK: {}", format_timestamp(SystemTime::now()), requested_path);
-            log_request(&client_ip, &format!("{} {} 200", method, path));
-            
-            // Send response
-            stream.write_all(header.as_bytes())?;
-            stream.write_all(&contents)?;
+
+    let requested_path = match fs::metadata(&requested_path) {
+        Ok(metadata) if metadata.is_dir() => {
+            // A directory's links (and its index, if it has none of its
+            // own) are only correct relative to a URL ending in `/`;
+            // redirect to that form first, matching Apache/nginx.
+            if !path.ends_with('/') {
+                return redirect_response(&format!("{}/", path));
+            }
+            match find_index_file(&requested_path) {
+                Some(index_path) => index_path,
+                None => return directory_listing(&requested_path, path),
+            }
         },
-        Err(e) => {
-            // Log error to console
-            println!("[{}] 404 Not Found: {} - {}", format_timestamp(SystemTime::now()), requested_path, e);
-            send_not_found(&mut stream)?;
+        Ok(_) => requested_path,
+        Err(_) => return not_found_response(),
+    };
+
+    let metadata = match fs::metadata(&requested_path) {
+        Ok(metadata) if metadata.is_file() => metadata,
+        _ => return not_found_response(),
+    };
+    let content_type = get_content_type(&requested_path);
+    let file_len = metadata.len();
+
+    // Media seeking and resumable downloads: honor a `Range` request by
+    // streaming just the requested slice of the file.
+    if let Some(range_header) = request.header("range") {
+        return match parse_range(range_header, file_len) {
+            Some((start, end)) => Response::partial_file(requested_path, content_type, start, end, file_len),
+            None => error_response(416, "Range Not Satisfiable"),
+        };
+    }
+
+    // Stream large files in chunks instead of buffering them whole.
+    if file_len > STREAMING_THRESHOLD {
+        return Response::chunked_file(requested_path, content_type);
+    }
+
+    match fs::read(&requested_path) {
+        Ok(contents) => Response::new(200, "OK", content_type, contents),
+        Err(_) => not_found_response(),
+    }
+}
+
+/// Parses a single-range `Range: bytes=start-end` header (also
+/// accepting the open-ended `start-` and suffix `-length` forms) into
+/// an inclusive `(start, end)` pair, clamped to `file_len`. Returns
+/// `None` if the header is malformed or the range can't be satisfied.
+fn parse_range(header: &str, file_len: u64) -> Option<(u64, u64)> {
+    let spec = header.strip_prefix("bytes=")?;
+    // Only a single range is supported; take the first if more were sent.
+    let spec = spec.split(',').next()?.trim();
+    let (start_str, end_str) = spec.split_once('-')?;
+
+    if start_str.is_empty() {
+        let suffix_len: u64 = end_str.parse().ok()?;
+        if suffix_len == 0 || file_len == 0 {
+            return None;
         }
+        let suffix_len = suffix_len.min(file_len);
+        return Some((file_len - suffix_len, file_len - 1));
     }
-    
-    stream.flush()?;
-    Ok(())
+
+    let start: u64 = start_str.parse().ok()?;
+    let end = if end_str.is_empty() {
+        file_len.checked_sub(1)?
+    } else {
+        end_str.parse::<u64>().ok()?.min(file_len.saturating_sub(1))
+    };
+
+    if file_len == 0 || start > end || start >= file_len {
+        return None;
+    }
+
+    Some((start, end))
 }
 
-fn send_not_found(stream: &mut TcpStream) -> io::Result<()> {
-    // Try to use custom 404 page if available
-    let (contents, status_line) = match fs::read("server_assets/404.html") {
-        Ok(data) => (data, "HTTP/1.1 404 NOT FOUND"),
-        Err(_) => (b"404 Not Found".to_vec(), "HTTP/1.1 404 NOT FOUND"),
+/// Tries each of `INDEX_FILENAMES` inside `dir` in turn, returning the
+/// path of the first one that exists as a regular file.
+fn find_index_file(dir: &str) -> Option<String> {
+    INDEX_FILENAMES
+        .iter()
+        .map(|name| format!("{}/{}", dir.trim_end_matches('/'), name))
+        .find(|candidate| fs::metadata(candidate).map(|m| m.is_file()).unwrap_or(false))
+}
+
+/// Generates an HTML directory listing for the directory on disk at
+/// `dir`, reached by `request_path` (which always ends in `/`; callers
+/// redirect otherwise). Links and the displayed title are built from
+/// `request_path`, not `dir`, so they resolve correctly regardless of
+/// where the webroot lives on disk and don't leak the filesystem
+/// layout to clients.
+fn directory_listing(dir: &str, request_path: &str) -> Response {
+    let entries = match fs::read_dir(dir) {
+        Ok(entries) => entries,
+        Err(_) => return not_found_response(),
     };
-    
-    let header = format!(
-        "{}\r\n\
-        Content-Length: {}\r\n\
-        Content-Type: text/html\r\n\
-        Access-Control-Allow-Origin: *\r\n\
-        Access-Control-Allow-Methods: GET, POST, OPTIONS\r\n\
-        Access-Control-Allow-Headers: Content-Type\r\n\
-        \r\n",
-        status_line,
-        contents.len()
-    );
-    
-    stream.write_all(header.as_bytes())?;
-    stream.write_all(&contents)?;
-    stream.flush()?;
-    Ok(())
+
+    let mut names: Vec<String> = entries
+        .filter_map(|entry| entry.ok())
+        .map(|entry| entry.file_name().to_string_lossy().into_owned())
+        .collect();
+    names.sort();
+
+    let mut body = format!("<html><body><h1>Index of {}</h1><ul>", escape_html(request_path));
+    if let Some(parent) = parent_request_path(request_path) {
+        body.push_str(&format!("<li><a href=\"{}\">..</a></li>", escape_html(&parent)));
+    }
+    for name in names {
+        let href = format!("{}{}", request_path, name);
+        body.push_str(&format!("<li><a href=\"{}\">{}</a></li>", escape_html(&href), escape_html(&name)));
+    }
+    body.push_str("</ul></body></html>");
+
+    Response::new(200, "OK", "text/html", body)
 }
 
-fn send_error(stream: &mut TcpStream, code: u16, message: &str) -> io::Result<()> {
-    let body = format!("<html><body><h1>{} {}</h1></body></html>", code, message);
-    let header = format!(
-        "HTTP/1.1 {} {}\r\n\
-        Content-Length: {}\r\n\
-        Content-Type: text/html\r\n\
-        Access-Control-Allow-Origin: *\r\n\
-        \r\n",
-        code,
-        message,
-        body.len()
-    );
-    
-    stream.write_all(header.as_bytes())?;
-    stream.write_all(body.as_bytes())?;
-    stream.flush()?;
-    Ok(())
+/// The `..` link target for a directory listing at `request_path`
+/// (which always ends in `/`): the enclosing directory's own
+/// trailing-slash URL, or `None` at the webroot.
+fn parent_request_path(request_path: &str) -> Option<String> {
+    if request_path == "/" {
+        return None;
+    }
+    let trimmed = request_path.trim_end_matches('/');
+    let idx = trimmed.rfind('/')?;
+    Some(format!("{}/", &trimmed[..idx]))
 }
 
-fn send_cors_preflight(stream: &mut TcpStream) -> io::Result<()> {
-    let response = "HTTP/1.1 204 No Content\r\n\
-                   Access-Control-Allow-Origin: *\r\n\
-                   Access-Control-Allow-Methods: GET, POST, OPTIONS\r\n\
-                   Access-Control-Allow-Headers: Content-Type\r\n\
-                   \r\n";
-    
-    stream.write_all(response.as_bytes())?;
-    stream.flush()?;
-    Ok(())
+fn escape_html(value: &str) -> String {
+    value.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;").replace('"', "&quot;")
+}
+
+/// A `301 Moved Permanently` redirect to `location`.
+fn redirect_response(location: &str) -> Response {
+    let mut response = Response::empty(301, "Moved Permanently");
+    response.headers.insert("Location".to_string(), location.to_string());
+    response
+}
+
+fn handle_visitor_count(visitor_count: &Arc<Mutex<u32>>) -> Response {
+    let count = match visitor_count.lock() {
+        Ok(mut guard) => {
+            // Always increment for now
+            *guard += 1;
+            println!("Incrementing visitor count to: {}", *guard);
+            *guard
+        },
+        Err(_) => {
+            eprintln!("Visitor count mutex was poisoned");
+            return error_response(500, "Internal Server Error");
+        }
+    };
+
+    Response::new(200, "OK", "text/plain", count.to_string())
+}
+
+fn not_found_response() -> Response {
+    // Try to use a custom 404 page if available
+    match fs::read("server_assets/404.html") {
+        Ok(contents) => Response::new(404, "NOT FOUND", "text/html", contents),
+        Err(_) => Response::new(404, "NOT FOUND", "text/html", "404 Not Found"),
+    }
+}
+
+fn error_response(code: u16, message: &str) -> Response {
+    let body = format!("<html><body><h1>{} {}</h1></body></html>", code, message);
+    Response::new(code, message, "text/html", body)
 }
 
 /// Returns a simple MIME type based on the file extension
-fn get_content_type(filename: &str) -> &str {
+fn get_content_type(filename: &str) -> &'static str {
     if filename.ends_with(".html") {
          "text/html"
     } else if filename.ends_with(".css") {
@@ -295,62 +446,160 @@ fn get_content_type(filename: &str) -> &str {
     }
 }
 
-fn rate_limit(ip: &str, ip_requests: &Arc<Mutex<HashMap<String, Vec<SystemTime>>>>) -> bool {
-    let ip_requests_guard = match ip_requests.lock() {
-        Ok(guard) => guard,
-        Err(_) => {
-            eprintln!("Failed to acquire lock for rate limiting");
-            return true; // Default to allowing if lock fails
-        }
-    };
-    
-    let mut ip_requests = ip_requests_guard;
-    let now = SystemTime::now();
-    let requests = ip_requests.entry(ip.to_string()).or_insert_with(Vec::new);
-    
-    // Clean up old requests
-    requests.retain(|&time| {
-        match now.duration_since(time) {
-            Ok(duration) => duration < RATE_LIMIT_WINDOW,
-            Err(_) => false, // Remove if time calculation fails
-        }
-    });
-    
-    // Check if rate limit is exceeded
-    if requests.len() >= MAX_REQUESTS_PER_WINDOW {
-        return false;
-    }
-    
-    // Add current request
-    requests.push(now);
-    true
-}
+#[cfg(test)]
+mod tests {
+    use super::*;
 
-fn log_request(ip: &str, request: &str) {
-    let timestamp = format_timestamp(SystemTime::now());
-    let log_entry = format!("[{}] {} - {}\n", timestamp, ip, request);
-    
-    match fs::OpenOptions::new()
-        .create(true)
-        .append(true)
-        .open("server.log")
-    {
-        Ok(mut file) => {
-            if let Err(e) = file.write_all(log_entry.as_bytes()) {
-                eprintln!("Failed to write to log file: {}", e);
-            }
-        },
-        Err(e) => eprintln!("Failed to open log file: {}", e),
+    #[test]
+    fn parse_range_start_end() {
+        assert_eq!(parse_range("bytes=0-499", 1000), Some((0, 499)));
     }
-}
 
-fn format_timestamp(time: SystemTime) -> String {
-    match time.duration_since(UNIX_EPOCH) {
-        Ok(duration) => {
-            let secs = duration.as_secs();
-            let nanos = duration.subsec_nanos();
-            format!("{}.{:09}", secs, nanos)
-        },
-        Err(_) => "unknown_time".to_string(),
+    #[test]
+    fn parse_range_open_ended() {
+        assert_eq!(parse_range("bytes=500-", 1000), Some((500, 999)));
+    }
+
+    #[test]
+    fn parse_range_suffix() {
+        assert_eq!(parse_range("bytes=-500", 1000), Some((500, 999)));
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn parse_range_rejects_start_past_end_of_file() {
+        assert_eq!(parse_range("bytes=1000-1500", 1000), None);
+    }
+
+    #[test]
+    fn parse_range_rejects_malformed_header() {
+        assert_eq!(parse_range("not-a-range", 1000), None);
+    }
+
+    #[test]
+    fn parse_range_rejects_empty_file() {
+        assert_eq!(parse_range("bytes=0-10", 0), None);
+    }
+
+    #[test]
+    fn parse_trusted_origins_trims_and_drops_empty_entries() {
+        assert_eq!(
+            parse_trusted_origins(" https://a.example , https://b.example,,"),
+            vec!["https://a.example", "https://b.example"]
+        );
+    }
+
+    #[test]
+    fn parse_trusted_origins_of_empty_string_is_empty() {
+        assert!(parse_trusted_origins("").is_empty());
+    }
+
+    #[test]
+    fn get_content_type_returned_value_outlives_its_argument() {
+        // Regression test: `get_content_type` must not borrow from its
+        // argument, since callers move the path alongside the content
+        // type it was derived from.
+        let content_type = {
+            let filename = String::from("index.html");
+            get_content_type(&filename)
+        };
+        assert_eq!(content_type, "text/html");
+    }
+
+    #[test]
+    fn escape_html_escapes_all_special_characters() {
+        assert_eq!(escape_html("<a> & \"b\""), "&lt;a&gt; &amp; &quot;b&quot;");
+    }
+
+    #[test]
+    fn parent_request_path_of_a_nested_directory() {
+        assert_eq!(parent_request_path("/a/b/"), Some("/a/".to_string()));
+        assert_eq!(parent_request_path("/a/"), Some("/".to_string()));
+    }
+
+    #[test]
+    fn parent_request_path_of_the_webroot_is_none() {
+        assert_eq!(parent_request_path("/"), None);
+    }
+
+    fn unique_test_id() -> u64 {
+        use std::sync::atomic::{AtomicU64, Ordering};
+        static COUNTER: AtomicU64 = AtomicU64::new(0);
+        COUNTER.fetch_add(1, Ordering::Relaxed)
+    }
+
+    fn unique_temp_dir(label: &str) -> std::path::PathBuf {
+        let dir = std::env::temp_dir()
+            .join(format!("rustwebserver-test-{}-{}-{}", std::process::id(), label, unique_test_id()));
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    #[test]
+    fn find_index_file_picks_the_first_existing_entry() {
+        let dir = unique_temp_dir("index-picks-first");
+        fs::write(dir.join("index.htm"), "htm").unwrap();
+        fs::write(dir.join("index.txt"), "txt").unwrap();
+
+        let found = find_index_file(dir.to_str().unwrap());
+        assert_eq!(found, Some(format!("{}/index.htm", dir.to_str().unwrap())));
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn find_index_file_falls_through_to_none_when_absent() {
+        let dir = unique_temp_dir("index-none");
+        fs::write(dir.join("notes.txt"), "x").unwrap();
+
+        assert_eq!(find_index_file(dir.to_str().unwrap()), None);
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn directory_listing_links_resolve_against_the_request_path_not_the_disk_path() {
+        let dir = unique_temp_dir("listing");
+        fs::write(dir.join("a.txt"), "a").unwrap();
+        fs::write(dir.join("<b>.txt"), "b").unwrap();
+
+        let response = directory_listing(dir.to_str().unwrap(), "/downloads/");
+        let body = match &response.body {
+            crate::response::Body::Bytes(bytes) => String::from_utf8(bytes.clone()).unwrap(),
+            _ => panic!("expected a buffered HTML body"),
+        };
+
+        assert!(body.contains("Index of /downloads/"), "title should show the request path: {body}");
+        assert!(!body.contains(dir.to_str().unwrap()), "listing must not leak the disk path: {body}");
+        assert!(body.contains("href=\"/downloads/a.txt\">a.txt<"), "link must be absolute from the request path: {body}");
+        assert!(
+            body.contains("href=\"/downloads/&lt;b&gt;.txt\">&lt;b&gt;.txt<"),
+            "entry names must be escaped both in the link and its text: {body}"
+        );
+        assert!(body.contains("href=\"/\">..<"), "parent link should point at the enclosing directory: {body}");
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn terminal_handler_redirects_a_bare_directory_request_to_its_trailing_slash_form() {
+        let dir_name = format!("rws-test-{}-{}", std::process::id(), unique_test_id());
+        let relative_dir = format!("public_html/{}", dir_name);
+        fs::create_dir_all(&relative_dir).unwrap();
+
+        let request = Request {
+            method: "GET".to_string(),
+            path: format!("/{}", dir_name),
+            version: "HTTP/1.1".to_string(),
+            headers: std::collections::BTreeMap::new(),
+            body: Vec::new(),
+            client_ip: String::new(),
+        };
+        let visitor_count = Arc::new(Mutex::new(0));
+
+        let response = terminal_handler(&request, &visitor_count);
+        assert_eq!(response.status, 301);
+        assert_eq!(response.headers.get("Location"), Some(&format!("/{}/", dir_name)));
+
+        fs::remove_dir_all(&relative_dir).unwrap();
+    }
+}
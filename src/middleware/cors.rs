@@ -0,0 +1,133 @@
+use super::Middleware;
+use crate::request::Request;
+use crate::response::Response;
+
+/// Configurable CORS headers: which origins, methods, and headers are
+/// allowed for cross-origin requests. Also answers `OPTIONS` preflight
+/// requests directly, short-circuiting the chain before the terminal
+/// handler runs.
+pub struct Cors {
+    pub allowed_origins: Vec<String>,
+    pub allowed_methods: Vec<String>,
+    pub allowed_headers: Vec<String>,
+}
+
+impl Cors {
+    /// Allows any origin, mirroring the `Access-Control-Allow-Origin: *`
+    /// the server sent on every response before middleware existed.
+    pub fn permissive() -> Cors {
+        Cors {
+            allowed_origins: vec!["*".to_string()],
+            allowed_methods: vec!["GET".to_string(), "POST".to_string(), "OPTIONS".to_string()],
+            allowed_headers: vec!["Content-Type".to_string()],
+        }
+    }
+
+    fn allow_origin_header(&self, request: &Request) -> Option<String> {
+        if self.allowed_origins.iter().any(|origin| origin == "*") {
+            return Some("*".to_string());
+        }
+
+        request
+            .header("origin")
+            .filter(|origin| self.allowed_origins.iter().any(|allowed| allowed == origin))
+            .map(str::to_string)
+    }
+
+    fn apply_headers(&self, request: &Request, response: &mut Response) {
+        if let Some(origin) = self.allow_origin_header(request) {
+            response.headers.insert("Access-Control-Allow-Origin".to_string(), origin);
+            response
+                .headers
+                .insert("Access-Control-Allow-Methods".to_string(), self.allowed_methods.join(", "));
+            response
+                .headers
+                .insert("Access-Control-Allow-Headers".to_string(), self.allowed_headers.join(", "));
+        }
+    }
+}
+
+impl Middleware for Cors {
+    fn before(&self, request: &Request) -> Option<Response> {
+        if request.method != "OPTIONS" {
+            return None;
+        }
+
+        let mut response = Response::empty(204, "No Content");
+        self.apply_headers(request, &mut response);
+        Some(response)
+    }
+
+    fn after(&self, request: &Request, response: &mut Response) {
+        self.apply_headers(request, response);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::BTreeMap;
+
+    fn request(method: &str, origin: Option<&str>) -> Request {
+        let mut headers = BTreeMap::new();
+        if let Some(origin) = origin {
+            headers.insert("origin".to_string(), origin.to_string());
+        }
+        Request {
+            method: method.to_string(),
+            path: "/".to_string(),
+            version: "HTTP/1.1".to_string(),
+            headers,
+            body: Vec::new(),
+            client_ip: String::new(),
+        }
+    }
+
+    #[test]
+    fn before_answers_options_preflight_directly() {
+        let cors = Cors::permissive();
+        let response = cors.before(&request("OPTIONS", Some("https://example.com"))).unwrap();
+
+        assert_eq!(response.status, 204);
+        assert_eq!(response.headers.get("Access-Control-Allow-Origin"), Some(&"*".to_string()));
+    }
+
+    #[test]
+    fn before_lets_non_options_requests_through() {
+        let cors = Cors::permissive();
+        assert!(cors.before(&request("GET", None)).is_none());
+    }
+
+    #[test]
+    fn allow_origin_header_is_wildcard_when_permissive() {
+        let cors = Cors::permissive();
+        assert_eq!(cors.allow_origin_header(&request("GET", Some("https://example.com"))), Some("*".to_string()));
+    }
+
+    #[test]
+    fn allow_origin_header_only_echoes_a_configured_origin() {
+        let cors = Cors {
+            allowed_origins: vec!["https://trusted.example".to_string()],
+            allowed_methods: vec!["GET".to_string()],
+            allowed_headers: vec![],
+        };
+
+        assert_eq!(
+            cors.allow_origin_header(&request("GET", Some("https://trusted.example"))),
+            Some("https://trusted.example".to_string())
+        );
+        assert_eq!(cors.allow_origin_header(&request("GET", Some("https://untrusted.example"))), None);
+        assert_eq!(cors.allow_origin_header(&request("GET", None)), None);
+    }
+
+    #[test]
+    fn after_adds_cors_headers_to_an_allowed_origin() {
+        let cors = Cors::permissive();
+        let mut response = Response::empty(200, "OK");
+
+        cors.after(&request("GET", Some("https://example.com")), &mut response);
+
+        assert_eq!(response.headers.get("Access-Control-Allow-Origin"), Some(&"*".to_string()));
+        assert!(response.headers.contains_key("Access-Control-Allow-Methods"));
+    }
+}
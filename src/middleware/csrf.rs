@@ -0,0 +1,87 @@
+use super::Middleware;
+use crate::request::Request;
+use crate::response::Response;
+
+/// Rejects state-changing requests whose `Origin` header isn't one of
+/// the configured trusted origins. `GET`/`HEAD`/`OPTIONS` are always
+/// let through since they shouldn't mutate state.
+pub struct CsrfOriginCheck {
+    pub trusted_origins: Vec<String>,
+}
+
+impl Middleware for CsrfOriginCheck {
+    fn before(&self, request: &Request) -> Option<Response> {
+        if matches!(request.method.as_str(), "GET" | "HEAD" | "OPTIONS") {
+            return None;
+        }
+
+        match request.header("origin") {
+            Some(origin) if self.trusted_origins.iter().any(|trusted| trusted == origin) => None,
+            _ => Some(Response::new(
+                403,
+                "Forbidden",
+                "text/plain",
+                "CSRF origin check failed",
+            )),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::BTreeMap;
+
+    fn request(method: &str, origin: Option<&str>) -> Request {
+        let mut headers = BTreeMap::new();
+        if let Some(origin) = origin {
+            headers.insert("origin".to_string(), origin.to_string());
+        }
+        Request {
+            method: method.to_string(),
+            path: "/".to_string(),
+            version: "HTTP/1.1".to_string(),
+            headers,
+            body: Vec::new(),
+            client_ip: String::new(),
+        }
+    }
+
+    #[test]
+    fn safe_methods_are_always_let_through() {
+        let check = CsrfOriginCheck { trusted_origins: Vec::new() };
+        for method in ["GET", "HEAD", "OPTIONS"] {
+            assert!(check.before(&request(method, None)).is_none(), "{method} should bypass the check");
+        }
+    }
+
+    #[test]
+    fn mutating_request_with_a_trusted_origin_is_allowed() {
+        let check = CsrfOriginCheck { trusted_origins: vec!["https://trusted.example".to_string()] };
+        assert!(check.before(&request("POST", Some("https://trusted.example"))).is_none());
+    }
+
+    #[test]
+    fn mutating_request_with_an_untrusted_origin_is_rejected() {
+        let check = CsrfOriginCheck { trusted_origins: vec!["https://trusted.example".to_string()] };
+        let response = check.before(&request("POST", Some("https://evil.example"))).unwrap();
+        assert_eq!(response.status, 403);
+    }
+
+    #[test]
+    fn mutating_request_with_no_origin_header_is_rejected() {
+        let check = CsrfOriginCheck { trusted_origins: vec!["https://trusted.example".to_string()] };
+        let response = check.before(&request("POST", None)).unwrap();
+        assert_eq!(response.status, 403);
+    }
+
+    #[test]
+    fn an_empty_trusted_origins_list_rejects_every_mutating_request() {
+        // Regression test: this is the exact bug chunk0-4's fix commit
+        // addressed — an empty list must not be mistaken for "allow
+        // everything".
+        let check = CsrfOriginCheck { trusted_origins: Vec::new() };
+        let response = check.before(&request("POST", Some("https://example.com"))).unwrap();
+        assert_eq!(response.status, 403);
+    }
+}
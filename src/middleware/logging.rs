@@ -0,0 +1,47 @@
+use std::fs;
+use std::io::Write;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use super::Middleware;
+use crate::request::Request;
+use crate::response::Response;
+
+/// Prints each request/response to the console and appends a line to
+/// `server.log`, replacing the prints that used to be scattered across
+/// every branch of the request handler.
+pub struct Logging;
+
+impl Middleware for Logging {
+    fn after(&self, request: &Request, response: &mut Response) {
+        let timestamp = format_timestamp(SystemTime::now());
+        println!(
+            "[{}] {} {} {} {} from {}",
+            timestamp, response.status, response.reason, request.method, request.path, request.client_ip
+        );
+        log_to_file(
+            &request.client_ip,
+            &format!("{} {} {}", request.method, request.path, response.status),
+        );
+    }
+}
+
+fn log_to_file(ip: &str, request: &str) {
+    let timestamp = format_timestamp(SystemTime::now());
+    let log_entry = format!("[{}] {} - {}\n", timestamp, ip, request);
+
+    match fs::OpenOptions::new().create(true).append(true).open("server.log") {
+        Ok(mut file) => {
+            if let Err(e) = file.write_all(log_entry.as_bytes()) {
+                eprintln!("Failed to write to log file: {}", e);
+            }
+        },
+        Err(e) => eprintln!("Failed to open log file: {}", e),
+    }
+}
+
+fn format_timestamp(time: SystemTime) -> String {
+    match time.duration_since(UNIX_EPOCH) {
+        Ok(duration) => format!("{}.{:09}", duration.as_secs(), duration.subsec_nanos()),
+        Err(_) => "unknown_time".to_string(),
+    }
+}
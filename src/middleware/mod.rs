@@ -0,0 +1,65 @@
+pub mod cors;
+pub mod csrf;
+pub mod logging;
+pub mod rate_limit;
+
+use crate::request::Request;
+use crate::response::Response;
+
+/// A single cross-cutting concern that wraps the terminal handler,
+/// modeled on actix-web's middleware: a `before` hook that can inspect
+/// the request and optionally short-circuit the response, and an
+/// `after` hook that can annotate whatever response is on its way out.
+pub trait Middleware: Send + Sync {
+    /// Runs before the terminal handler, in chain order. Returning
+    /// `Some(response)` short-circuits the chain: the terminal handler
+    /// and any later middleware's `before` never run.
+    fn before(&self, _request: &Request) -> Option<Response> {
+        None
+    }
+
+    /// Runs after a response exists — either from the terminal handler
+    /// or from an earlier `before` short-circuit — in reverse chain
+    /// order, so the first middleware added gets the outermost say.
+    fn after(&self, _request: &Request, _response: &mut Response) {}
+}
+
+/// An ordered chain of middleware wrapped around a terminal handler.
+pub struct Chain {
+    middleware: Vec<Box<dyn Middleware>>,
+}
+
+impl Chain {
+    pub fn new() -> Chain {
+        Chain { middleware: Vec::new() }
+    }
+
+    /// Appends a middleware to the end of the chain.
+    pub fn with(mut self, middleware: impl Middleware + 'static) -> Chain {
+        self.middleware.push(Box::new(middleware));
+        self
+    }
+
+    /// Runs the chain around `terminal`, which produces the response
+    /// when no middleware short-circuits first.
+    pub fn run(&self, request: &Request, terminal: impl FnOnce(&Request) -> Response) -> Response {
+        let mut short_circuited = None;
+        let mut ran = 0;
+
+        for middleware in &self.middleware {
+            ran += 1;
+            if let Some(response) = middleware.before(request) {
+                short_circuited = Some(response);
+                break;
+            }
+        }
+
+        let mut response = short_circuited.unwrap_or_else(|| terminal(request));
+
+        for middleware in self.middleware[..ran].iter().rev() {
+            middleware.after(request, &mut response);
+        }
+
+        response
+    }
+}
@@ -0,0 +1,98 @@
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::{Duration, SystemTime};
+
+use super::Middleware;
+use crate::request::Request;
+use crate::response::Response;
+
+/// Caps each client IP to `max_requests` within a sliding `window`,
+/// tracked per-IP as a list of recent request timestamps.
+pub struct RateLimiter {
+    window: Duration,
+    max_requests: usize,
+    requests: Mutex<HashMap<String, Vec<SystemTime>>>,
+}
+
+impl RateLimiter {
+    pub fn new(window: Duration, max_requests: usize) -> RateLimiter {
+        RateLimiter { window, max_requests, requests: Mutex::new(HashMap::new()) }
+    }
+
+    fn allow(&self, ip: &str) -> bool {
+        let mut requests = match self.requests.lock() {
+            Ok(guard) => guard,
+            Err(_) => {
+                eprintln!("Failed to acquire lock for rate limiting");
+                return true; // Default to allowing if lock fails
+            }
+        };
+
+        let now = SystemTime::now();
+        let window = self.window;
+        let entries = requests.entry(ip.to_string()).or_insert_with(Vec::new);
+
+        // Clean up old requests
+        entries.retain(|&time| matches!(now.duration_since(time), Ok(d) if d < window));
+
+        if entries.len() >= self.max_requests {
+            return false;
+        }
+
+        entries.push(now);
+        true
+    }
+}
+
+impl Middleware for RateLimiter {
+    fn before(&self, request: &Request) -> Option<Response> {
+        if self.allow(&request.client_ip) {
+            None
+        } else {
+            println!("Rate limit exceeded for {}", request.client_ip);
+            Some(Response::new(429, "Too Many Requests", "text/plain", "Rate limit exceeded"))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn request_from(ip: &str) -> Request {
+        Request {
+            method: "GET".to_string(),
+            path: "/".to_string(),
+            version: "HTTP/1.1".to_string(),
+            headers: std::collections::BTreeMap::new(),
+            body: Vec::new(),
+            client_ip: ip.to_string(),
+        }
+    }
+
+    #[test]
+    fn allows_requests_up_to_the_limit() {
+        let limiter = RateLimiter::new(Duration::from_secs(60), 3);
+        for _ in 0..3 {
+            assert!(limiter.before(&request_from("1.2.3.4")).is_none());
+        }
+    }
+
+    #[test]
+    fn rejects_requests_past_the_limit() {
+        let limiter = RateLimiter::new(Duration::from_secs(60), 3);
+        for _ in 0..3 {
+            assert!(limiter.before(&request_from("1.2.3.4")).is_none());
+        }
+
+        let response = limiter.before(&request_from("1.2.3.4")).unwrap();
+        assert_eq!(response.status, 429);
+    }
+
+    #[test]
+    fn tracks_each_client_ip_independently() {
+        let limiter = RateLimiter::new(Duration::from_secs(60), 1);
+        assert!(limiter.before(&request_from("1.2.3.4")).is_none());
+        assert!(limiter.before(&request_from("5.6.7.8")).is_none(), "a different IP must have its own budget");
+    }
+}
@@ -0,0 +1,83 @@
+use std::sync::{mpsc, Arc, Mutex};
+use std::thread;
+
+/// Work handed to a worker thread: a boxed closure to run to completion.
+type Job = Box<dyn FnOnce() + Send + 'static>;
+
+/// A fixed-size pool of long-lived worker threads fed by an `mpsc`
+/// channel of jobs, so one slow connection occupies only the worker
+/// running it instead of blocking the acceptor from handing off new
+/// work to the rest of the pool.
+pub struct ThreadPool {
+    workers: Vec<Worker>,
+    sender: Option<mpsc::Sender<Job>>,
+}
+
+impl ThreadPool {
+    /// Spawns `size` worker threads, each pulling jobs from the shared
+    /// channel until the pool is dropped. Panics if `size` is 0.
+    pub fn new(size: usize) -> ThreadPool {
+        assert!(size > 0);
+
+        let (sender, receiver) = mpsc::channel();
+        let receiver = Arc::new(Mutex::new(receiver));
+
+        let mut workers = Vec::with_capacity(size);
+        for id in 0..size {
+            workers.push(Worker::new(id, Arc::clone(&receiver)));
+        }
+
+        ThreadPool { workers, sender: Some(sender) }
+    }
+
+    /// Queues `job` to run on the next free worker.
+    pub fn execute<F>(&self, job: F)
+    where
+        F: FnOnce() + Send + 'static,
+    {
+        if let Some(sender) = &self.sender {
+            // The receiver only disappears when the pool itself is
+            // being dropped, so this send cannot fail in practice.
+            let _ = sender.send(Box::new(job));
+        }
+    }
+}
+
+impl Drop for ThreadPool {
+    fn drop(&mut self) {
+        // Dropping the sender closes the channel, which lets each
+        // worker's `recv` loop end once drained, so we can join them.
+        drop(self.sender.take());
+
+        for worker in &mut self.workers {
+            if let Some(handle) = worker.handle.take() {
+                if let Err(e) = handle.join() {
+                    eprintln!("Error joining worker {}: {:?}", worker.id, e);
+                }
+            }
+        }
+    }
+}
+
+struct Worker {
+    id: usize,
+    handle: Option<thread::JoinHandle<()>>,
+}
+
+impl Worker {
+    fn new(id: usize, receiver: Arc<Mutex<mpsc::Receiver<Job>>>) -> Worker {
+        let handle = thread::spawn(move || loop {
+            let job = match receiver.lock() {
+                Ok(guard) => guard.recv(),
+                Err(_) => break, // Mutex poisoned; stop taking new work.
+            };
+
+            match job {
+                Ok(job) => job(),
+                Err(_) => break, // Sender dropped; pool is shutting down.
+            }
+        });
+
+        Worker { id, handle: Some(handle) }
+    }
+}
@@ -0,0 +1,209 @@
+use std::collections::BTreeMap;
+use std::io::{self, Read};
+
+/// Headers are buffered until we see this separator, after which any
+/// remaining bytes in the buffer belong to the body.
+const HEADER_TERMINATOR: &[u8] = b"\r\n\r\n";
+
+/// Refuse to buffer more than this many bytes of headers from a single
+/// client before giving up, so a misbehaving connection can't grow the
+/// buffer without bound.
+const MAX_HEADER_BYTES: usize = 64 * 1024;
+
+/// A fully parsed HTTP request: method, path, headers, and body.
+///
+/// Follows the approach used by the MOROS `httpd.rs` `Request::from`
+/// parser: read the status line, accumulate header lines until the
+/// blank `\r\n\r\n` separator, then read exactly `Content-Length` more
+/// bytes from the stream into `body`.
+#[derive(Debug, Clone)]
+pub struct Request {
+    pub method: String,
+    pub path: String,
+    pub version: String,
+    pub headers: BTreeMap<String, String>,
+    #[allow(dead_code)] // part of the parsed request; no handler reads bodies yet
+    pub body: Vec<u8>,
+    /// The peer's IP address, filled in by the caller after parsing
+    /// (the parser itself only sees a generic `Read` stream, which may
+    /// not expose a socket address).
+    pub client_ip: String,
+}
+
+impl Request {
+    /// Reads and parses one HTTP request from `stream`.
+    ///
+    /// `leftover` carries bytes across calls on the same connection: any
+    /// bytes read past this request's boundary (the start of a
+    /// pipelined next request) are left in it instead of being
+    /// discarded, and bytes a previous call stashed there are consumed
+    /// first. Callers keep-alive-looping on one socket must reuse the
+    /// same `leftover` buffer for every call; a fresh connection starts
+    /// with an empty one.
+    ///
+    /// Returns `Ok(None)` if the client closed the connection before
+    /// sending anything, which callers should treat as a clean
+    /// disconnect rather than an error.
+    ///
+    /// Generic over `Read` so the same parser serves plain `TcpStream`
+    /// connections and TLS-wrapped streams alike.
+    pub fn from_stream<S: Read>(stream: &mut S, leftover: &mut Vec<u8>) -> io::Result<Option<Request>> {
+        let mut raw = std::mem::take(leftover);
+        let mut buf = [0u8; 4096];
+
+        let header_end = loop {
+            if let Some(pos) = find_subslice(&raw, HEADER_TERMINATOR) {
+                break pos;
+            }
+
+            if raw.len() > MAX_HEADER_BYTES {
+                return Err(io::Error::new(io::ErrorKind::InvalidData, "headers too large"));
+            }
+
+            let n = stream.read(&mut buf)?;
+            if n == 0 {
+                if raw.is_empty() {
+                    return Ok(None);
+                }
+                return Err(io::Error::new(
+                    io::ErrorKind::UnexpectedEof,
+                    "connection closed before headers were complete",
+                ));
+            }
+            raw.extend_from_slice(&buf[..n]);
+        };
+
+        let header_str = String::from_utf8_lossy(&raw[..header_end]);
+        let mut lines = header_str.lines();
+
+        let status_line = lines
+            .next()
+            .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidData, "missing status line"))?;
+        let mut parts = status_line.split_whitespace();
+        let method = parts
+            .next()
+            .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidData, "malformed status line"))?
+            .to_string();
+        let path = parts
+            .next()
+            .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidData, "malformed status line"))?
+            .to_string();
+        let version = parts.next().unwrap_or("HTTP/1.0").to_string();
+
+        let mut headers = BTreeMap::new();
+        for line in lines {
+            if let Some((name, value)) = line.split_once(':') {
+                headers.insert(name.trim().to_ascii_lowercase(), value.trim().to_string());
+            }
+        }
+
+        let content_length = headers
+            .get("content-length")
+            .and_then(|v| v.parse::<usize>().ok())
+            .unwrap_or(0);
+
+        let body_start = header_end + HEADER_TERMINATOR.len();
+        while raw.len() < body_start + content_length {
+            let n = stream.read(&mut buf)?;
+            if n == 0 {
+                break;
+            }
+            raw.extend_from_slice(&buf[..n]);
+        }
+
+        // Anything read beyond this request's body belongs to whatever
+        // comes next on the connection (a pipelined request, or just
+        // unread bytes if the client isn't keeping it alive); stash it
+        // in `leftover` instead of dropping it.
+        let body_end = (body_start + content_length).min(raw.len());
+        *leftover = raw[body_end..].to_vec();
+        let body = raw[body_start..body_end].to_vec();
+
+        Ok(Some(Request { method, path, version, headers, body, client_ip: String::new() }))
+    }
+
+    /// Returns the header value for `name`, matched case-insensitively.
+    pub fn header(&self, name: &str) -> Option<&str> {
+        self.headers.get(&name.to_ascii_lowercase()).map(String::as_str)
+    }
+
+    /// Whether the client wants this connection kept open for further
+    /// requests: an explicit `Connection: keep-alive`/`close` header
+    /// wins, otherwise it's the HTTP/1.1 default of keep-alive.
+    pub fn wants_keep_alive(&self) -> bool {
+        match self.header("connection") {
+            Some(value) => !value.eq_ignore_ascii_case("close"),
+            None => self.version == "HTTP/1.1",
+        }
+    }
+}
+
+fn find_subslice(haystack: &[u8], needle: &[u8]) -> Option<usize> {
+    haystack.windows(needle.len()).position(|window| window == needle)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_a_simple_get() {
+        let mut stream = b"GET /index.html HTTP/1.1\r\nHost: example.com\r\n\r\n".as_slice();
+        let mut leftover = Vec::new();
+        let request = Request::from_stream(&mut stream, &mut leftover).unwrap().unwrap();
+
+        assert_eq!(request.method, "GET");
+        assert_eq!(request.path, "/index.html");
+        assert_eq!(request.version, "HTTP/1.1");
+        assert_eq!(request.header("host"), Some("example.com"));
+        assert!(leftover.is_empty());
+    }
+
+    #[test]
+    fn reads_exactly_content_length_bytes_of_body() {
+        let mut stream = b"POST /submit HTTP/1.1\r\nContent-Length: 5\r\n\r\nhello".as_slice();
+        let mut leftover = Vec::new();
+        let request = Request::from_stream(&mut stream, &mut leftover).unwrap().unwrap();
+
+        assert_eq!(request.body, b"hello");
+        assert!(leftover.is_empty());
+    }
+
+    #[test]
+    fn returns_none_on_immediate_disconnect() {
+        let mut stream = b"".as_slice();
+        let mut leftover = Vec::new();
+        assert!(Request::from_stream(&mut stream, &mut leftover).unwrap().is_none());
+    }
+
+    #[test]
+    fn stashes_a_pipelined_second_request_in_leftover_instead_of_dropping_it() {
+        // Two requests arrive back to back in a single read, as a
+        // pipelining client might send them.
+        let mut stream = b"GET /first HTTP/1.1\r\n\r\nGET /second HTTP/1.1\r\n\r\n".as_slice();
+        let mut leftover = Vec::new();
+
+        let first = Request::from_stream(&mut stream, &mut leftover).unwrap().unwrap();
+        assert_eq!(first.path, "/first");
+        assert!(!leftover.is_empty(), "the second request's bytes must be preserved, not dropped");
+
+        let second = Request::from_stream(&mut stream, &mut leftover).unwrap().unwrap();
+        assert_eq!(second.path, "/second");
+        assert!(leftover.is_empty());
+    }
+
+    #[test]
+    fn preserves_pipelined_bytes_arriving_after_a_body() {
+        // The next request's header bytes arrive in the same read as
+        // the current request's body.
+        let mut stream =
+            b"POST /submit HTTP/1.1\r\nContent-Length: 5\r\n\r\nhelloGET /next HTTP/1.1\r\n\r\n".as_slice();
+        let mut leftover = Vec::new();
+
+        let first = Request::from_stream(&mut stream, &mut leftover).unwrap().unwrap();
+        assert_eq!(first.body, b"hello");
+
+        let second = Request::from_stream(&mut stream, &mut leftover).unwrap().unwrap();
+        assert_eq!(second.path, "/next");
+    }
+}
@@ -0,0 +1,151 @@
+use std::collections::BTreeMap;
+use std::fs::File;
+use std::io::{self, Read, Seek, SeekFrom, Write};
+
+/// Chunk size used both when streaming a `Transfer-Encoding: chunked`
+/// body and when copying a `Range` response from disk.
+const CHUNK_SIZE: usize = 64 * 1024;
+
+/// How a response body is produced when it's written out.
+#[derive(Debug, Clone)]
+pub enum Body {
+    /// A body already fully buffered in memory.
+    Bytes(Vec<u8>),
+    /// Stream the whole file in fixed-size chunks with
+    /// `Transfer-Encoding: chunked`, instead of buffering it, so
+    /// serving a large file doesn't cost memory proportional to its
+    /// size.
+    Chunked { path: String },
+    /// Stream the inclusive byte range `start..=end` of the file for a
+    /// `Range` request, sent with a normal `Content-Length` since the
+    /// length is known up front.
+    Range { path: String, start: u64, end: u64 },
+}
+
+/// An HTTP response under construction: status, headers, and body.
+///
+/// Built up by the terminal handler and then mutated in place by
+/// middleware `after` hooks (e.g. to add CORS headers) before being
+/// written out to the client.
+#[derive(Debug, Clone)]
+pub struct Response {
+    pub status: u16,
+    pub reason: String,
+    pub headers: BTreeMap<String, String>,
+    pub body: Body,
+}
+
+impl Response {
+    /// Builds a response with the given status, a `Content-Type`
+    /// header, and a body already in memory.
+    pub fn new(status: u16, reason: &str, content_type: &str, body: impl AsRef<[u8]>) -> Response {
+        let mut headers = BTreeMap::new();
+        headers.insert("Content-Type".to_string(), content_type.to_string());
+
+        Response {
+            status,
+            reason: reason.to_string(),
+            headers,
+            body: Body::Bytes(body.as_ref().to_vec()),
+        }
+    }
+
+    /// Builds a response with no body, for statuses like `204 No
+    /// Content` or `101 Switching Protocols`.
+    pub fn empty(status: u16, reason: &str) -> Response {
+        Response { status, reason: reason.to_string(), headers: BTreeMap::new(), body: Body::Bytes(Vec::new()) }
+    }
+
+    /// Builds a `200 OK` response that streams `path` in chunks rather
+    /// than buffering it whole, for files over the streaming threshold.
+    pub fn chunked_file(path: String, content_type: &str) -> Response {
+        let mut headers = BTreeMap::new();
+        headers.insert("Content-Type".to_string(), content_type.to_string());
+        headers.insert("Transfer-Encoding".to_string(), "chunked".to_string());
+
+        Response { status: 200, reason: "OK".to_string(), headers, body: Body::Chunked { path } }
+    }
+
+    /// Builds a `206 Partial Content` response streaming the inclusive
+    /// byte range `start..=end` of the file at `path`, whose full size
+    /// is `total_len`.
+    pub fn partial_file(path: String, content_type: &str, start: u64, end: u64, total_len: u64) -> Response {
+        let mut headers = BTreeMap::new();
+        headers.insert("Content-Type".to_string(), content_type.to_string());
+        headers.insert("Accept-Ranges".to_string(), "bytes".to_string());
+        headers.insert("Content-Range".to_string(), format!("bytes {}-{}/{}", start, end, total_len));
+
+        Response {
+            status: 206,
+            reason: "Partial Content".to_string(),
+            headers,
+            body: Body::Range { path, start, end },
+        }
+    }
+
+    /// Writes the status line, headers, and body to `stream`.
+    pub fn write_to<S: Write>(&self, stream: &mut S) -> io::Result<()> {
+        match &self.body {
+            Body::Bytes(bytes) => self.write_buffered(stream, bytes),
+            Body::Chunked { path } => self.write_chunked(stream, path),
+            Body::Range { path, start, end } => self.write_range(stream, path, *start, *end),
+        }
+    }
+
+    fn write_status_and_headers<S: Write>(&self, stream: &mut S, content_length: Option<u64>) -> io::Result<()> {
+        let mut header = format!("HTTP/1.1 {} {}\r\n", self.status, self.reason);
+        if let Some(len) = content_length {
+            header.push_str(&format!("Content-Length: {}\r\n", len));
+        }
+        for (name, value) in &self.headers {
+            header.push_str(&format!("{}: {}\r\n", name, value));
+        }
+        header.push_str("\r\n");
+
+        stream.write_all(header.as_bytes())
+    }
+
+    fn write_buffered<S: Write>(&self, stream: &mut S, bytes: &[u8]) -> io::Result<()> {
+        self.write_status_and_headers(stream, Some(bytes.len() as u64))?;
+        stream.write_all(bytes)?;
+        stream.flush()
+    }
+
+    fn write_chunked<S: Write>(&self, stream: &mut S, path: &str) -> io::Result<()> {
+        self.write_status_and_headers(stream, None)?;
+
+        let mut file = File::open(path)?;
+        let mut buf = [0u8; CHUNK_SIZE];
+        loop {
+            let n = file.read(&mut buf)?;
+            if n == 0 {
+                break;
+            }
+            write!(stream, "{:x}\r\n", n)?;
+            stream.write_all(&buf[..n])?;
+            stream.write_all(b"\r\n")?;
+        }
+        stream.write_all(b"0\r\n\r\n")?;
+        stream.flush()
+    }
+
+    fn write_range<S: Write>(&self, stream: &mut S, path: &str, start: u64, end: u64) -> io::Result<()> {
+        let mut remaining = end - start + 1;
+        self.write_status_and_headers(stream, Some(remaining))?;
+
+        let mut file = File::open(path)?;
+        file.seek(SeekFrom::Start(start))?;
+
+        let mut buf = [0u8; CHUNK_SIZE];
+        while remaining > 0 {
+            let to_read = remaining.min(buf.len() as u64) as usize;
+            let n = file.read(&mut buf[..to_read])?;
+            if n == 0 {
+                break;
+            }
+            stream.write_all(&buf[..n])?;
+            remaining -= n as u64;
+        }
+        stream.flush()
+    }
+}
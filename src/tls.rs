@@ -0,0 +1,61 @@
+use std::fs::File;
+use std::io::{self, BufReader};
+use std::net::TcpStream;
+use std::sync::Arc;
+
+use rustls::server::ServerConnection;
+use rustls::{Certificate, PrivateKey, ServerConfig, StreamOwned};
+
+/// Where to find the certificate chain and private key used to
+/// terminate incoming TLS connections.
+#[derive(Debug, Clone)]
+pub struct TlsConfig {
+    pub cert_path: String,
+    pub key_path: String,
+}
+
+/// A handshake-complete TLS connection wrapping a `TcpStream`.
+///
+/// Implements `Read`/`Write` so it can be passed anywhere a plain
+/// `TcpStream` is accepted, letting `handle_client` stay oblivious to
+/// whether the connection is encrypted.
+pub type TlsStream = StreamOwned<ServerConnection, TcpStream>;
+
+/// Builds a `rustls::ServerConfig` from a PEM certificate chain and
+/// private key on disk. Call this once at startup; the resulting
+/// config is cheap to clone (it's an `Arc`) and shared across every
+/// accepted connection.
+pub fn load_server_config(config: &TlsConfig) -> io::Result<Arc<ServerConfig>> {
+    let certs = load_certs(&config.cert_path)?;
+    let key = load_private_key(&config.key_path)?;
+
+    let server_config = ServerConfig::builder()
+        .with_safe_defaults()
+        .with_no_client_auth()
+        .with_single_cert(certs, key)
+        .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+
+    Ok(Arc::new(server_config))
+}
+
+/// Completes a TLS handshake on an already-accepted `TcpStream`,
+/// blocking until the handshake finishes or fails.
+pub fn accept(config: &Arc<ServerConfig>, stream: TcpStream) -> io::Result<TlsStream> {
+    let connection = ServerConnection::new(Arc::clone(config)).map_err(io::Error::other)?;
+    Ok(StreamOwned::new(connection, stream))
+}
+
+fn load_certs(path: &str) -> io::Result<Vec<Certificate>> {
+    let mut reader = BufReader::new(File::open(path)?);
+    let raw_certs = rustls_pemfile::certs(&mut reader)?;
+    Ok(raw_certs.into_iter().map(Certificate).collect())
+}
+
+fn load_private_key(path: &str) -> io::Result<PrivateKey> {
+    let mut reader = BufReader::new(File::open(path)?);
+    let keys = rustls_pemfile::pkcs8_private_keys(&mut reader)?;
+    keys.into_iter()
+        .next()
+        .map(PrivateKey)
+        .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidData, "no private key found in key file"))
+}
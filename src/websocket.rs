@@ -0,0 +1,244 @@
+use std::io::{self, Read, Write};
+
+use base64::Engine;
+use sha1::{Digest, Sha1};
+
+use crate::request::Request;
+use crate::response::Response;
+
+/// The fixed GUID the WebSocket protocol appends to the client's key
+/// before hashing, per RFC 6455 section 1.3.
+const WEBSOCKET_GUID: &str = "258EAFA5-E914-47DA-95CA-C5AB0DC85B11";
+
+const OPCODE_TEXT: u8 = 0x1;
+const OPCODE_BINARY: u8 = 0x2;
+const OPCODE_CLOSE: u8 = 0x8;
+const OPCODE_PING: u8 = 0x9;
+const OPCODE_PONG: u8 = 0xA;
+
+/// Refuse to allocate a buffer for a frame payload larger than this,
+/// mirroring `MAX_HEADER_BYTES` in request.rs: the extended length
+/// field is attacker-controlled, and allocating it unchecked before
+/// reading a single payload byte lets a crafted header (e.g. a 64-bit
+/// length of `u64::MAX`) abort the worker thread with a capacity
+/// overflow.
+const MAX_FRAME_PAYLOAD: u64 = 16 * 1024 * 1024;
+
+/// Whether `request` is asking to upgrade this connection to a
+/// WebSocket: a `GET` with `Upgrade: websocket` and a `Sec-WebSocket-Key`.
+pub fn is_upgrade_request(request: &Request) -> bool {
+    request.method == "GET"
+        && request
+            .header("upgrade")
+            .map(|value| value.eq_ignore_ascii_case("websocket"))
+            .unwrap_or(false)
+        && request.header("sec-websocket-key").is_some()
+}
+
+/// Computes the `Sec-WebSocket-Accept` value for a `Sec-WebSocket-Key`:
+/// base64 of the SHA-1 hash of the key concatenated with the GUID.
+fn accept_key(client_key: &str) -> String {
+    let mut hasher = Sha1::new();
+    hasher.update(client_key.as_bytes());
+    hasher.update(WEBSOCKET_GUID.as_bytes());
+    base64::engine::general_purpose::STANDARD.encode(hasher.finalize())
+}
+
+/// Builds the `101 Switching Protocols` handshake response for
+/// `client_key`. Returned as an ordinary `Response` — rather than
+/// written directly to the stream — so the handshake passes through
+/// the same middleware chain (logging, rate limiting) as every other
+/// request instead of special-casing the upgrade ahead of it.
+pub fn handshake_response(client_key: &str) -> Response {
+    let mut response = Response::empty(101, "Switching Protocols");
+    response.headers.insert("Upgrade".to_string(), "websocket".to_string());
+    response.headers.insert("Connection".to_string(), "Upgrade".to_string());
+    response.headers.insert("Sec-WebSocket-Accept".to_string(), accept_key(client_key));
+    response
+}
+
+/// A decoded, unmasked WebSocket frame.
+enum Frame {
+    Text(String),
+    Binary(Vec<u8>),
+    Ping(Vec<u8>),
+    /// We never look at a pong's payload, only that one arrived.
+    Pong,
+    Close,
+}
+
+/// Reads and unmasks one client frame from `stream`. Client frames are
+/// always masked, per RFC 6455 section 5.3. Returns `None` if the
+/// client closed the connection before sending a frame header.
+fn read_frame<S: Read>(stream: &mut S) -> io::Result<Option<Frame>> {
+    let mut header = [0u8; 2];
+    if !read_exact_or_eof(stream, &mut header)? {
+        return Ok(None);
+    }
+
+    let opcode = header[0] & 0x0F;
+    let masked = header[1] & 0x80 != 0;
+    let mut len = u64::from(header[1] & 0x7F);
+
+    if len == 126 {
+        let mut ext = [0u8; 2];
+        stream.read_exact(&mut ext)?;
+        len = u64::from(u16::from_be_bytes(ext));
+    } else if len == 127 {
+        let mut ext = [0u8; 8];
+        stream.read_exact(&mut ext)?;
+        len = u64::from_be_bytes(ext);
+    }
+
+    if len > MAX_FRAME_PAYLOAD {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            "frame payload too large",
+        ));
+    }
+
+    let mask = if masked {
+        let mut mask = [0u8; 4];
+        stream.read_exact(&mut mask)?;
+        Some(mask)
+    } else {
+        None
+    };
+
+    let mut payload = vec![0u8; len as usize];
+    stream.read_exact(&mut payload)?;
+
+    if let Some(mask) = mask {
+        for (i, byte) in payload.iter_mut().enumerate() {
+            *byte ^= mask[i % 4];
+        }
+    }
+
+    let frame = match opcode {
+        OPCODE_TEXT => Frame::Text(String::from_utf8_lossy(&payload).into_owned()),
+        OPCODE_PING => Frame::Ping(payload),
+        OPCODE_PONG => Frame::Pong,
+        OPCODE_CLOSE => Frame::Close,
+        _ => Frame::Binary(payload), // Binary, continuation, and anything unknown.
+    };
+
+    Ok(Some(frame))
+}
+
+/// Like `Read::read_exact`, but treats an immediate EOF (zero bytes
+/// read before any data arrives) as a clean disconnect instead of an
+/// `UnexpectedEof` error.
+fn read_exact_or_eof<S: Read>(stream: &mut S, buf: &mut [u8]) -> io::Result<bool> {
+    let mut total = 0;
+    while total < buf.len() {
+        let n = stream.read(&mut buf[total..])?;
+        if n == 0 {
+            if total == 0 {
+                return Ok(false);
+            }
+            return Err(io::Error::new(io::ErrorKind::UnexpectedEof, "connection closed mid-frame"));
+        }
+        total += n;
+    }
+    Ok(true)
+}
+
+/// Writes an unmasked, unfragmented server-to-client frame (servers
+/// never mask their frames, per RFC 6455 section 5.1).
+fn write_frame<S: Write>(stream: &mut S, opcode: u8, payload: &[u8]) -> io::Result<()> {
+    let mut header = vec![0x80 | opcode];
+
+    let len = payload.len();
+    if len < 126 {
+        header.push(len as u8);
+    } else if len <= u16::MAX as usize {
+        header.push(126);
+        header.extend_from_slice(&(len as u16).to_be_bytes());
+    } else {
+        header.push(127);
+        header.extend_from_slice(&(len as u64).to_be_bytes());
+    }
+
+    stream.write_all(&header)?;
+    stream.write_all(payload)?;
+    stream.flush()
+}
+
+/// Runs the post-handshake frame loop: echoes text/binary frames back
+/// to the client and answers pings with pongs, until a close frame
+/// arrives or the connection ends.
+pub fn echo_loop<S: Read + Write>(stream: &mut S) -> io::Result<()> {
+    loop {
+        let frame = match read_frame(stream)? {
+            Some(frame) => frame,
+            None => return Ok(()),
+        };
+
+        match frame {
+            Frame::Text(text) => write_frame(stream, OPCODE_TEXT, text.as_bytes())?,
+            Frame::Binary(data) => write_frame(stream, OPCODE_BINARY, &data)?,
+            Frame::Ping(payload) => write_frame(stream, OPCODE_PONG, &payload)?,
+            Frame::Pong => {},
+            Frame::Close => {
+                write_frame(stream, OPCODE_CLOSE, &[])?;
+                return Ok(());
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn masked_frame(opcode: u8, payload: &[u8]) -> Vec<u8> {
+        let mask = [0x12, 0x34, 0x56, 0x78];
+        let mut frame = vec![0x80 | opcode, 0x80 | payload.len() as u8];
+        frame.extend_from_slice(&mask);
+        frame.extend(payload.iter().enumerate().map(|(i, b)| b ^ mask[i % 4]));
+        frame
+    }
+
+    #[test]
+    fn read_frame_decodes_masked_text() {
+        let bytes = masked_frame(OPCODE_TEXT, b"hello");
+        let mut input = bytes.as_slice();
+        let frame = read_frame(&mut input).unwrap().unwrap();
+        assert!(matches!(frame, Frame::Text(text) if text == "hello"));
+    }
+
+    #[test]
+    fn read_frame_returns_none_on_clean_disconnect() {
+        let mut input: &[u8] = &[];
+        assert!(read_frame(&mut input).unwrap().is_none());
+    }
+
+    #[test]
+    fn handshake_response_computes_the_rfc6455_example_accept_key() {
+        // The worked example from RFC 6455 section 1.3.
+        let response = handshake_response("dGhlIHNhbXBsZSBub25jZQ==");
+        assert_eq!(response.status, 101);
+        assert_eq!(
+            response.headers.get("Sec-WebSocket-Accept"),
+            Some(&"s3pPLMBiTxaQ9kYGzzhZRbK+xOo=".to_string())
+        );
+        assert_eq!(response.headers.get("Upgrade"), Some(&"websocket".to_string()));
+        assert_eq!(response.headers.get("Connection"), Some(&"Upgrade".to_string()));
+    }
+
+    #[test]
+    fn read_frame_rejects_oversized_extended_length() {
+        // A masked frame header claiming the maximum possible 64-bit
+        // payload length, with no payload bytes behind it. Before the
+        // size cap this would try to allocate ~16 exabytes and abort
+        // the process with a capacity overflow instead of returning an
+        // error.
+        let mut header = vec![0x80 | OPCODE_BINARY, 0x80 | 127];
+        header.extend_from_slice(&u64::MAX.to_be_bytes());
+        header.extend_from_slice(&[0u8; 4]); // mask
+
+        let mut input = header.as_slice();
+        let result = read_frame(&mut input);
+        assert!(result.is_err());
+    }
+}